@@ -2,19 +2,67 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput, Data, Fields, Attribute, Lit};
 
+/// Where a field's value lives in the positional array.
+///
+/// `Explicit` is a plain `#[order(n)]` field occupying a single slot.
+/// `Flatten` is a `#[order(flatten, n)]` field whose own ordered type occupies
+/// a contiguous run of slots starting at `n`; its width is only known once the
+/// nested type's `__ORDERED_WIDTH` const is resolved by the compiler.
+enum FieldOrder {
+    Explicit(usize),
+    Flatten(usize),
+}
+
+impl FieldOrder {
+    fn start(&self) -> usize {
+        match self {
+            FieldOrder::Explicit(index) | FieldOrder::Flatten(index) => *index,
+        }
+    }
+}
+
+/// What to fall back to when a field's index is absent from a shorter-than-maximum
+/// array, via `#[order(n, default)]` or `#[order(n, default = "path::to::fn")]`.
+enum FieldDefault {
+    Default,
+    Path(syn::Path),
+}
+
+impl quote::ToTokens for FieldDefault {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            FieldDefault::Default => quote! { Default::default() }.to_tokens(tokens),
+            FieldDefault::Path(path) => quote! { #path() }.to_tokens(tokens),
+        }
+    }
+}
+
 /// A derive macro that allows a struct to be deserialized from a JSON array
 /// with explicit field ordering.
-/// 
+///
 /// This macro allows fields to specify which array index they should deserialize from
-/// using the `#[order(n)]` attribute.
-#[proc_macro_derive(DeserializeOrdered, attributes(order))]
+/// using the `#[order(n)]` attribute. A field can instead use `#[order(flatten, n)]` to
+/// splice a nested ordered struct into the parent's index space starting at `n`.
+///
+/// If no field carries an `#[order(n)]` attribute, indices are inferred from declaration
+/// order instead (field 0 maps to index 0, field 1 to index 1, and so on) - handy for a
+/// tuple-like record that maps 1:1 onto the array. Mixing annotated and unannotated fields
+/// on the same struct is an error; either annotate every field or none.
+///
+/// By default any elements beyond the last wanted index are silently ignored. Add a
+/// container-level `#[ordered(strict)]` attribute to reject arrays carrying unexpected
+/// trailing elements instead.
+#[proc_macro_derive(DeserializeOrdered, attributes(order, ordered))]
 pub fn deserialize_ordered(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
     let input = parse_macro_input!(input as DeriveInput);
-    
+
     // Get the name of the struct
     let name = &input.ident;
-    
+
+    // Whether `#[ordered(strict)]` was applied to the struct itself
+    let strict = is_strict(&input.attrs);
+
     // Extract the fields from the struct
     let fields = match &input.data {
         Data::Struct(data_struct) => {
@@ -25,47 +73,131 @@ pub fn deserialize_ordered(input: TokenStream) -> TokenStream {
         },
         _ => panic!("DeserializeOrdered only supports structs"),
     };
-    
-    // Extract field names, types and their order attributes
-    let mut field_info = Vec::new();
-    
-    for field in fields {
-        let field_name = field.ident.as_ref().unwrap();
-        let field_type = &field.ty;
-        
-        // Find the order attribute
-        let order_attr = field.attrs.iter()
-            .find(|attr| attr.path().is_ident("order"))
-            .unwrap_or_else(|| panic!("Field `{}` is missing #[order(n)] attribute", field_name));
-        
-        // Extract the index from the order attribute
-        let index = extract_order_index(order_attr)
-            .unwrap_or_else(|| panic!("Invalid #[order(n)] attribute for field `{}`", field_name));
-        
-        field_info.push((field_name, field_type, index));
+
+    // Extract field names, types, and their order/default attributes
+    let field_info = collect_field_orders(fields, name);
+
+    // Sort the fields by their starting index so we can read the sequence
+    // positionally in a single forward pass
+    let mut sorted_indices: Vec<usize> = (0..field_info.len()).collect();
+    sorted_indices.sort_by_key(|&i| field_info[i].2.start());
+
+    // The struct's own width in the positional array: the end of its
+    // last field in index order. For a flatten field this folds in the
+    // nested type's own `__ORDERED_WIDTH`, so the expression is only fully
+    // resolved once the compiler type-checks the generated code.
+    let mut width_expr = quote! { 0usize };
+    for &i in &sorted_indices {
+        let (_, field_type, order, _) = &field_info[i];
+        width_expr = match order {
+            FieldOrder::Explicit(idx) => quote! { #idx + 1 },
+            FieldOrder::Flatten(start) => quote! { #start + <#field_type>::__ORDERED_WIDTH },
+        };
     }
-    
-    // Find the maximum array index we need to access
-    let max_index = field_info.iter()
-        .map(|(_, _, index)| *index)
-        .max()
-        .unwrap_or(0);
-    
-    // Create field mapping expressions for each field
-    let field_mapping = field_info.iter().map(|(field_name, field_type, index)| {
-        let idx = *index; // Dereference here to use the actual usize value
+
+    // For each field in index order, drain any gap since the previous wanted
+    // index with `IgnoredAny`, then read the field's value directly off the
+    // sequence - no intermediate `Value` buffer, no clones. A flatten field
+    // delegates its run of slots to the nested type's own `__read_ordered_fields`,
+    // reusing the same `SeqAccess` in place rather than treating it as a fresh
+    // top-level sequence, so it only consumes its own slots and leaves whatever
+    // follows in the parent untouched. A field with a declared default falls
+    // back to it instead of erroring when the array ends before its index is
+    // reached.
+    let field_reads = sorted_indices.iter().map(|&i| {
+        let (field_name, field_type, order, default) = &field_info[i];
+        match order {
+            FieldOrder::Explicit(index) => {
+                let missing = match default {
+                    Some(default) => quote! { #default },
+                    None => quote! {
+                        return Err(de::Error::invalid_length(
+                            #index,
+                            &"an array long enough to reach every required index",
+                        ))
+                    },
+                };
+                quote! {
+                    if #index > cursor {
+                        for _ in 0..(#index - cursor) {
+                            seq.next_element::<de::IgnoredAny>()?;
+                        }
+                    }
+                    let #field_name: #field_type = match seq.next_element::<#field_type>()? {
+                        Some(value) => value,
+                        None => #missing,
+                    };
+                    cursor = #index + 1;
+                }
+            },
+            FieldOrder::Flatten(start) => {
+                quote! {
+                    if #start > cursor {
+                        for _ in 0..(#start - cursor) {
+                            seq.next_element::<de::IgnoredAny>()?;
+                        }
+                    }
+                    let #field_name: #field_type = <#field_type>::__read_ordered_fields(&mut seq)?;
+                    cursor = #start + <#field_type>::__ORDERED_WIDTH;
+                }
+            },
+        }
+    });
+
+    // Build the struct literal from the locals bound above, in the struct's
+    // original declaration order
+    let field_mapping = field_info.iter().map(|(field_name, _, _, _)| {
+        quote! { #field_name }
+    });
+
+    // Only the genuinely top-level call needs to fully consume the sequence
+    // (most self-describing formats require the whole top-level `SeqAccess` to
+    // be drained before they'll find the closing delimiter); a flatten field
+    // reads its slots via `__read_ordered_fields` directly and must NOT drain,
+    // since that would eat elements meant for the parent struct's later fields.
+    // In non-strict mode we drain and discard whatever is left; in strict mode
+    // we error if anything is left.
+    let trailing_check = if strict {
         quote! {
-            #field_name: {
-                let value = &array_elements[#idx];
-                serde_json::from_value::<#field_type>(value.clone())
-                    .map_err(|e| serde::de::Error::custom(format!("Failed to deserialize field `{}` at index {}: {}", 
-                        stringify!(#field_name), #idx, e)))?
+            if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::custom(format!(
+                    "unexpected trailing element: array has more than the expected {} elements",
+                    #name::__ORDERED_WIDTH
+                )));
             }
         }
-    });
-    
+    } else {
+        quote! {
+            while seq.next_element::<de::IgnoredAny>()?.is_some() {}
+        }
+    };
+
     // Generate the implementation
     let expanded = quote! {
+        impl #name {
+            #[doc(hidden)]
+            pub const __ORDERED_WIDTH: usize = #width_expr;
+
+            // Reads this struct's own fields from a `SeqAccess` positioned at
+            // its first slot, without touching anything beyond its declared
+            // width. Used both by the top-level `Deserialize` impl below and
+            // by parent structs that flatten this type into their own array.
+            #[doc(hidden)]
+            pub fn __read_ordered_fields<'de, A>(mut seq: A) -> Result<Self, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                use serde::de;
+
+                let mut cursor = 0usize;
+                #(#field_reads)*
+
+                Ok(#name {
+                    #(#field_mapping),*
+                })
+            }
+        }
+
         impl<'de> serde::Deserialize<'de> for #name {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
             where
@@ -74,47 +206,30 @@ pub fn deserialize_ordered(input: TokenStream) -> TokenStream {
                 use serde::de::{self, Visitor, SeqAccess};
                 use std::fmt;
                 use std::marker::PhantomData;
-                
+
                 struct ArrayVisitor<'de> {
                     marker: PhantomData<#name>,
                     lifetime: PhantomData<&'de ()>,
                 }
-                
+
                 impl<'de> Visitor<'de> for ArrayVisitor<'de> {
                     type Value = #name;
-                    
+
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str(&format!("a JSON array with at least {} elements", #max_index + 1))
+                        formatter.write_str(&format!("a JSON array laid out over {} positional slots", #name::__ORDERED_WIDTH))
                     }
-                    
+
                     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
                     where
                         A: SeqAccess<'de>,
                     {
-                        // Store all elements up to max_index
-                        let mut array_elements: Vec<serde_json::Value> = vec![serde_json::Value::Null; #max_index + 1];
-                        
-                        // Collect array elements
-                        let mut index = 0;
-                        while let Some(value) = seq.next_element::<serde_json::Value>()? {
-                            if index <= #max_index {
-                                array_elements[index] = value;
-                            }
-                            index += 1;
-                        }
-                        
-                        // Check that we have all required indices
-                        if index <= #max_index {
-                            return Err(serde::de::Error::invalid_length(index, &self));
-                        }
-                        
-                        // Deserialize each field from its corresponding array element
-                        Ok(#name {
-                            #(#field_mapping),*
-                        })
+                        let value = #name::__read_ordered_fields(&mut seq)?;
+                        #trailing_check
+
+                        Ok(value)
                     }
                 }
-                
+
                 deserializer.deserialize_seq(ArrayVisitor {
                     marker: PhantomData,
                     lifetime: PhantomData,
@@ -122,16 +237,201 @@ pub fn deserialize_ordered(input: TokenStream) -> TokenStream {
             }
         }
     };
-    
+
     // Return the generated code as a token stream
     TokenStream::from(expanded)
 }
 
-// Helper function to extract the order index from an attribute
-fn extract_order_index(attr: &Attribute) -> Option<usize> {
-    // Parse the attribute meta
-    match attr.meta.require_list().ok()?.parse_args::<Lit>().ok() {
-        Some(Lit::Int(lit_int)) => lit_int.base10_parse::<usize>().ok(),
-        _ => None,
+// Collect each field's name, type, order, and optional default, honoring either explicit
+// `#[order(...)]` attributes on every field or none at all (inferring declaration-order
+// indices in the latter case). Shared by `DeserializeOrdered` and `SerializeOrdered` so
+// both derives read field attributes the same way; `SerializeOrdered` additionally
+// rejects `#[order(flatten, n)]` fields.
+fn collect_field_orders<'a>(
+    fields: &'a syn::punctuated::Punctuated<syn::Field, syn::token::Comma>,
+    name: &syn::Ident,
+) -> Vec<(&'a syn::Ident, &'a syn::Type, FieldOrder, Option<FieldDefault>)> {
+    let mut field_info = Vec::new();
+    let mut any_explicit = false;
+    let mut any_inferred = false;
+
+    for (position, field) in fields.iter().enumerate() {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+
+        // Find the order attribute, if any
+        let order_attr = field.attrs.iter().find(|attr| attr.path().is_ident("order"));
+
+        let (order, default) = match order_attr {
+            Some(attr) => {
+                any_explicit = true;
+                parse_order_attr(attr, field_name)
+            },
+            None => {
+                // No #[order(n)]: infer the index from declaration order
+                any_inferred = true;
+                (FieldOrder::Explicit(position), None)
+            },
+        };
+
+        if matches!(order, FieldOrder::Flatten(_)) && default.is_some() {
+            panic!("Field `{}`: #[order(flatten, ...)] fields cannot declare a default", field_name);
+        }
+
+        field_info.push((field_name, field_type, order, default));
+    }
+
+    if any_explicit && any_inferred {
+        panic!(
+            "{}: cannot mix fields with an explicit #[order(n)] and fields without one; \
+             annotate every field or none",
+            name
+        );
+    }
+
+    field_info
+}
+
+// Check whether the struct carries a container-level `#[ordered(strict)]` attribute
+fn is_strict(attrs: &[Attribute]) -> bool {
+    attrs.iter()
+        .filter(|attr| attr.path().is_ident("ordered"))
+        .any(|attr| {
+            attr.parse_args::<syn::Ident>()
+                .map(|ident| ident == "strict")
+                .unwrap_or(false)
+        })
+}
+
+// Parse a `#[order(n)]`, `#[order(flatten, n)]`, `#[order(n, default)]`, or
+// `#[order(n, default = "path::to::fn")]` attribute into a `FieldOrder` and
+// optional `FieldDefault`.
+fn parse_order_attr(attr: &Attribute, field_name: &syn::Ident) -> (FieldOrder, Option<FieldDefault>) {
+    let list = attr.meta.require_list()
+        .unwrap_or_else(|_| panic!("Invalid #[order(...)] attribute for field `{}`", field_name));
+
+    let args = list.parse_args_with(syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated)
+        .unwrap_or_else(|_| panic!("Invalid #[order(...)] attribute for field `{}`", field_name));
+
+    let mut flatten = false;
+    let mut index = None;
+    let mut default = None;
+
+    for expr in args {
+        match expr {
+            syn::Expr::Path(path) if path.path.is_ident("flatten") => flatten = true,
+            syn::Expr::Path(path) if path.path.is_ident("default") => default = Some(FieldDefault::Default),
+            syn::Expr::Lit(syn::ExprLit { lit: Lit::Int(lit_int), .. }) => {
+                index = Some(lit_int.base10_parse::<usize>()
+                    .unwrap_or_else(|_| panic!("Invalid #[order(...)] index for field `{}`", field_name)));
+            },
+            syn::Expr::Assign(assign) => {
+                let is_default_key = matches!(&*assign.left, syn::Expr::Path(p) if p.path.is_ident("default"));
+                if !is_default_key {
+                    panic!("Invalid #[order(...)] attribute for field `{}`", field_name);
+                }
+                let path_str = match &*assign.right {
+                    syn::Expr::Lit(syn::ExprLit { lit: Lit::Str(s), .. }) => s.value(),
+                    _ => panic!("Invalid #[order(default = ...)] value for field `{}`: expected a string path", field_name),
+                };
+                let path = syn::parse_str::<syn::Path>(&path_str)
+                    .unwrap_or_else(|_| panic!("Invalid default path `{}` for field `{}`", path_str, field_name));
+                default = Some(FieldDefault::Path(path));
+            },
+            _ => panic!("Invalid #[order(...)] attribute for field `{}`", field_name),
+        }
     }
+
+    let index = index
+        .unwrap_or_else(|| panic!("Field `{}` is missing an index in #[order(...)]", field_name));
+
+    let order = if flatten {
+        FieldOrder::Flatten(index)
+    } else {
+        FieldOrder::Explicit(index)
+    };
+
+    (order, default)
+}
+
+/// A derive macro that allows a struct to be serialized back into a JSON array
+/// with explicit field ordering.
+///
+/// This is the write-side companion to `DeserializeOrdered`: it reuses the same
+/// `#[order(n)]` attributes (including inferred declaration-order indices and
+/// `#[order(n, default)]`, whose default is irrelevant here and simply ignored)
+/// to emit a sequence of length `max_index + 1`, writing each field at its
+/// declared index and filling any skipped (sparse) positions with a unit
+/// placeholder. `#[order(flatten, n)]` fields are not yet supported here.
+#[proc_macro_derive(SerializeOrdered, attributes(order))]
+pub fn serialize_ordered(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree
+    let input = parse_macro_input!(input as DeriveInput);
+
+    // Get the name of the struct
+    let name = &input.ident;
+
+    // Extract the fields from the struct
+    let fields = match &input.data {
+        Data::Struct(data_struct) => {
+            match &data_struct.fields {
+                Fields::Named(fields_named) => &fields_named.named,
+                _ => panic!("SerializeOrdered only supports structs with named fields"),
+            }
+        },
+        _ => panic!("SerializeOrdered only supports structs"),
+    };
+
+    // Extract field names, types, and their order attributes the same way
+    // DeserializeOrdered does; flatten fields are then rejected below
+    let field_info = collect_field_orders(fields, name);
+
+    for (field_name, _, order, _) in &field_info {
+        if matches!(order, FieldOrder::Flatten(_)) {
+            panic!(
+                "Field `{}`: SerializeOrdered does not support #[order(flatten, ...)] fields yet",
+                field_name
+            );
+        }
+    }
+
+    // Find the maximum array index we need to emit
+    let max_index = field_info.iter()
+        .map(|(_, _, order, _)| order.start())
+        .max()
+        .unwrap_or(0);
+
+    // Build a serialize_element call for every slot from 0 to max_index, writing
+    // the matching field if one claims that index or a unit placeholder otherwise
+    let element_writes = (0..=max_index).map(|slot| {
+        match field_info.iter().find(|(_, _, order, _)| order.start() == slot) {
+            Some((field_name, _, _, _)) => quote! {
+                seq.serialize_element(&self.#field_name)?;
+            },
+            None => quote! {
+                seq.serialize_element(&())?;
+            },
+        }
+    });
+
+    let len = max_index + 1;
+
+    // Generate the implementation
+    let expanded = quote! {
+        impl serde::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::SerializeSeq;
+
+                let mut seq = serializer.serialize_seq(Some(#len))?;
+                #(#element_writes)*
+                seq.end()
+            }
+        }
+    };
+
+    // Return the generated code as a token stream
+    TokenStream::from(expanded)
 }
@@ -1,42 +1,107 @@
-use json_array_derive::DeserializeOrdered;
+use json_array_derive::{DeserializeOrdered, SerializeOrdered};
 use serde::{Deserialize, Serialize};
 
 // Example struct with fields matching array indices
-#[derive(Debug, DeserializeOrdered, Serialize)]
+#[derive(Debug, DeserializeOrdered, SerializeOrdered)]
 struct Person {
     #[order(0)]
     id: i32,
-    
+
     #[order(1)]
     name: String,
-    
+
     #[order(2)]
     height: f64,
 }
 
 // Example with out-of-order indices
-#[derive(Debug, DeserializeOrdered, Serialize)]
+#[derive(Debug, DeserializeOrdered, SerializeOrdered)]
 struct OutOfOrderPerson {
     #[order(2)]
     id: i32,
-    
+
     #[order(0)]
     name: String,
-    
+
     #[order(1)]
     height: f64,
 }
 
 // Example with sparse indices (skipping some array elements)
-#[derive(Debug, DeserializeOrdered, Serialize)]
+#[derive(Debug, DeserializeOrdered, SerializeOrdered)]
 struct SparsePerson {
     #[order(0)]
     id: i32,
-    
+
     #[order(4)]
     name: String,
-    
+
+    #[order(1)]
+    height: f64,
+}
+
+// Nested ordered struct, meant to be spliced into a parent's index space
+#[derive(Debug, DeserializeOrdered)]
+struct TeamSize {
+    #[order(0)]
+    players: i32,
+
+    #[order(1)]
+    coaches: i32,
+}
+
+// Example using `#[order(flatten, n)]` to splice TeamSize's two slots into
+// this struct's array starting at index 1
+#[derive(Debug, DeserializeOrdered)]
+struct Team {
+    #[order(0)]
+    name: String,
+
+    #[order(flatten, 1)]
+    size: TeamSize,
+
+    #[order(3)]
+    founded: i32,
+}
+
+// Strict mode: reject arrays carrying unexpected trailing elements
+#[derive(Debug, DeserializeOrdered)]
+#[ordered(strict)]
+struct StrictPerson {
+    #[order(0)]
+    id: i32,
+
+    #[order(1)]
+    name: String,
+}
+
+fn default_height() -> f64 {
+    1.8
+}
+
+// Example with optional trailing fields: a shorter array falls back to
+// `Default::default()` for `height` and to `default_height()` for `backup_height`
+#[derive(Debug, DeserializeOrdered)]
+struct EvolvingPerson {
+    #[order(0)]
+    id: i32,
+
     #[order(1)]
+    name: String,
+
+    #[order(2, default)]
+    height: f64,
+
+    #[order(3, default = "default_height")]
+    backup_height: f64,
+}
+
+// Tuple-like record: no #[order(n)] attributes, so indices are inferred from
+// declaration order (id -> 0, name -> 1, height -> 2)
+#[derive(Debug, DeserializeOrdered)]
+struct InferredPerson {
+    id: i32,
+    name: String,
     height: f64,
 }
 
@@ -68,4 +133,41 @@ fn main() {
     let json_array3 = r#"[99, 1.72, "ignore me", "also ignore", "Bob Johnson", "more to ignore"]"#;
     let sparse_person: SparsePerson = serde_json::from_str(json_array3).unwrap();
     println!("Deserialized from JSON array (sparse): {:?}", sparse_person);
+
+    // Test case 5: Serialize a struct back into its positional array form
+    let person_json = serde_json::to_string(&person).unwrap();
+    println!("Serialized back to JSON array (in-order): {}", person_json);
+
+    // Test case 6: Serialize a sparse struct, filling skipped indices with null
+    let sparse_json = serde_json::to_string(&sparse_person).unwrap();
+    println!("Serialized back to JSON array (sparse): {}", sparse_json);
+
+    // Test case 7: Deserialize with a flattened nested struct splicing into
+    // the parent's index space
+    let json_array4 = r#"["Wolves", 11, 3, 1999]"#;
+    let team: Team = serde_json::from_str(json_array4).unwrap();
+    println!("Deserialized with flatten: {:?}", team);
+
+    // Test case 8: Strict mode accepts an exact-length array
+    let json_array5 = r#"[7, "Carol"]"#;
+    let strict_person: StrictPerson = serde_json::from_str(json_array5).unwrap();
+    println!("Deserialized (strict, exact length): {:?}", strict_person);
+
+    // Test case 9: Strict mode rejects trailing elements
+    let json_array6 = r#"[7, "Carol", "unexpected"]"#;
+    match serde_json::from_str::<StrictPerson>(json_array6) {
+        Ok(value) => println!("Unexpectedly deserialized: {:?}", value),
+        Err(e) => println!("Strict mode rejected trailing element as expected: {}", e),
+    }
+
+    // Test case 10: A shorter array falls back to each field's declared default
+    let json_array7 = r#"[3, "Dana"]"#;
+    let evolving_person: EvolvingPerson = serde_json::from_str(json_array7).unwrap();
+    println!("Deserialized with defaults for missing trailing fields: {:?}", evolving_person);
+
+    // Test case 11: Deserialize a struct with no #[order(n)] attributes at all,
+    // relying on declaration order for the index mapping
+    let json_array8 = r#"[5, "Eve", 1.68]"#;
+    let inferred_person: InferredPerson = serde_json::from_str(json_array8).unwrap();
+    println!("Deserialized with inferred indices: {:?}", inferred_person);
 }